@@ -0,0 +1,5 @@
+// Fixture example: shares its name with `src/bin/probe.rs` so bintest's own ignored tests can
+// exercise `artifact_for()`/`command_for()` disambiguation between a `bin` and an `example`.
+fn main() {
+    println!("example probe");
+}
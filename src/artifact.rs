@@ -0,0 +1,31 @@
+use cargo_metadata::camino::Utf8PathBuf;
+
+/// Metadata describing one compiled executable: the package and target it was built from,
+/// the path to the binary, and the profile/feature configuration it was built with.
+///
+/// Mirroring the raw fields lets callers assert on which profile or features a given
+/// executable was compiled with, and disambiguate executables that happen to share a name.
+#[derive(Debug, Clone)]
+pub struct ExecutableArtifact {
+    /// The id of the package that owns the target this executable was built from, as cargo's
+    /// opaque `PackageId` renders it. Not suitable for matching on the package name; use
+    /// [`package_name`](Self::package_name) for that.
+    pub package_id: String,
+    /// The name of the package that owns the target this executable was built from, resolved
+    /// from `cargo metadata` rather than guessed from `package_id`.
+    pub package_name: String,
+    /// The cargo target name, e.g. the binary or example name as declared in `Cargo.toml`.
+    pub target_name: String,
+    /// The kinds of the target that produced this executable (e.g. `bin`, `example`, `test`).
+    pub target_kind: Vec<String>,
+    /// Path to the compiled executable.
+    pub path: Utf8PathBuf,
+    /// Optimization level cargo compiled this artifact with.
+    pub opt_level: String,
+    /// Whether this artifact was compiled with `debug_assertions` enabled.
+    pub debug_assertions: bool,
+    /// Features enabled for this artifact.
+    pub features: Vec<String>,
+    /// Whether cargo considered this artifact already up to date and skipped rebuilding it.
+    pub fresh: bool,
+}
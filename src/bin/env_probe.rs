@@ -0,0 +1,6 @@
+// Fixture binary used by bintest's own ignored tests to prove that `env()` entries set on the
+// builder actually reach the spawned 'cargo build' process: `option_env!` is resolved by rustc
+// at compile time, inheriting whatever environment the cargo build child was run with.
+fn main() {
+    println!("{}", option_env!("BINTEST_ENV_PROBE").unwrap_or("unset"));
+}
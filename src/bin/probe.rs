@@ -0,0 +1,5 @@
+// Fixture binary: shares its name with `examples/probe.rs` so bintest's own ignored tests can
+// exercise `artifact_for()`/`command_for()` disambiguation between a `bin` and an `example`.
+fn main() {
+    println!("bin probe");
+}
@@ -0,0 +1,49 @@
+use std::fmt;
+use std::process::ExitStatus;
+
+/// Errors that can occur while building or locating executables via `cargo build`.
+#[derive(Debug)]
+pub enum BinTestError {
+    /// Spawning the `cargo build` process, or waiting for it to exit, failed.
+    Spawn(std::io::Error),
+    /// Reading cargo's JSON message stream failed. Lines that are not valid JSON messages
+    /// are not an error; cargo_metadata passes them through as plain text lines.
+    Message(std::io::Error),
+    /// Running `cargo metadata` to resolve package names failed.
+    Metadata(cargo_metadata::Error),
+    /// `cargo build` finished unsuccessfully.
+    ///
+    /// Carries the rendered compiler diagnostics emitted during the build, in the order
+    /// cargo reported them, along with the process exit status.
+    BuildFailed {
+        diagnostics: Vec<String>,
+        status: ExitStatus,
+    },
+}
+
+impl fmt::Display for BinTestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Spawn(err) => write!(f, "failed to spawn or wait for 'cargo build': {err}"),
+            Self::Message(err) => write!(f, "failed to read 'cargo build' output: {err}"),
+            Self::Metadata(err) => write!(f, "failed to run 'cargo metadata': {err}"),
+            Self::BuildFailed { diagnostics, status } => {
+                write!(f, "'cargo build' failed with {status}")?;
+                for diagnostic in diagnostics {
+                    write!(f, "\nCaused by:\n  {diagnostic}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for BinTestError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Spawn(err) | Self::Message(err) => Some(err),
+            Self::Metadata(err) => Some(err),
+            Self::BuildFailed { .. } => None,
+        }
+    }
+}
@@ -0,0 +1,8 @@
+// Fixture integration-test harness used by bintest's own ignored tests to exercise
+// `test_command()`'s `--exact <filter>` against a real test binary.
+
+#[test]
+fn probe_one() {}
+
+#[test]
+fn probe_two() {}